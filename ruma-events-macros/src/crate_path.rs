@@ -0,0 +1,77 @@
+//! Resolves the crate paths used by generated code to refer to `ruma-events` and `serde`, so
+//! `ruma_event!` produces correct output both inside `ruma-events` itself and in an external
+//! crate that depends on it.
+
+use std::{env, fs, path::PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+/// The paths generated code uses to refer to `ruma-events`, `serde`, and `serde_json` items.
+pub struct CratePaths {
+    /// The path used for `ruma-events` items, such as `EventType`.
+    pub ruma_events: TokenStream,
+
+    /// The path used for `serde` items, such as `Serialize`/`Deserialize`.
+    pub serde: TokenStream,
+
+    /// The path used for `serde_json` items, such as `Value`.
+    pub serde_json: TokenStream,
+}
+
+impl CratePaths {
+    /// Resolves the paths to use.
+    ///
+    /// An explicit `#[ruma_event(crate = "...")]` override always wins. Otherwise, this detects
+    /// whether the macro is expanding inside `ruma-events` itself (by reading the consuming
+    /// crate's own `Cargo.toml`, found through `CARGO_MANIFEST_DIR`).
+    ///
+    /// Inside `ruma-events` itself, `serde`/`serde_json` are direct dependencies, so they're
+    /// referred to directly. Everywhere else — including an external consumer that doesn't use
+    /// the `crate` override — they're referred to through `ruma-events`'s own re-exports, so
+    /// generated code doesn't depend on the consumer having its own direct `serde`/`serde_json`
+    /// dependency (exactly the dependency `ruma_events` itself would otherwise require).
+    pub fn resolve(explicit: Option<&LitStr>) -> Self {
+        if let Some(explicit) = explicit {
+            let root: TokenStream =
+                explicit.value().parse().unwrap_or_else(|_| quote! { ::ruma_events });
+
+            return Self {
+                serde: quote! { #root::exports::serde },
+                serde_json: quote! { #root::exports::serde_json },
+                ruma_events: root,
+            };
+        }
+
+        if expanding_inside_ruma_events() {
+            Self {
+                ruma_events: quote! { crate },
+                serde: quote! { ::serde },
+                serde_json: quote! { ::serde_json },
+            }
+        } else {
+            Self {
+                ruma_events: quote! { ::ruma_events },
+                serde: quote! { ::ruma_events::exports::serde },
+                serde_json: quote! { ::ruma_events::exports::serde_json },
+            }
+        }
+    }
+}
+
+/// Checks whether the crate being expanded into is `ruma-events` itself, by reading its package
+/// name out of its own `Cargo.toml`.
+fn expanding_inside_ruma_events() -> bool {
+    let manifest_dir = match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => return false,
+    };
+
+    let manifest = match fs::read_to_string(manifest_dir.join("Cargo.toml")) {
+        Ok(manifest) => manifest,
+        Err(_) => return false,
+    };
+
+    manifest.lines().map(str::trim).any(|line| line == "name = \"ruma-events\"")
+}
@@ -0,0 +1,149 @@
+//! Codegen for the central `EventType` enum and content dispatcher, generated by the
+//! `ruma_event_types!` macro from the full set of `event_type => ContentType` mappings.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{Ident, LitStr};
+
+use crate::{
+    crate_path::CratePaths,
+    parse::{EventTypeMapping, EventTypeSetInput},
+};
+
+/// The result of processing the `ruma_event_types!` macro, ready for output back to source code.
+pub struct EventTypeSet {
+    /// Every `event_type => ContentType` mapping given to the macro.
+    mappings: Vec<EventTypeMapping>,
+}
+
+impl From<EventTypeSetInput> for EventTypeSet {
+    fn from(input: EventTypeSetInput) -> Self {
+        Self {
+            mappings: input.mappings,
+        }
+    }
+}
+
+impl ToTokens for EventTypeSet {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let variant_names: Vec<Ident> = self
+            .mappings
+            .iter()
+            .map(|mapping| variant_name(&mapping.event_type))
+            .collect();
+        let wire_strings: Vec<&LitStr> =
+            self.mappings.iter().map(|mapping| &mapping.event_type).collect();
+        let content_types: Vec<_> =
+            self.mappings.iter().map(|mapping| &mapping.content_type).collect();
+
+        // `ruma_event_types!` has no `#[ruma_event(crate = "...")]` of its own (it takes a list
+        // of mappings, not an attributed item), so only auto-detection applies here.
+        let crate_paths = CratePaths::resolve(None);
+        let serde_path = &crate_paths.serde;
+        let serde_json_path = &crate_paths.serde_json;
+
+        let expanded = quote! {
+            /// The type of a Matrix event, as given by its `type` field on the wire.
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            pub enum EventType {
+                #(
+                    #[doc = #wire_strings]
+                    #variant_names
+                ),*
+            }
+
+            impl ::std::str::FromStr for EventType {
+                type Err = ::std::string::String;
+
+                fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                    match s {
+                        #(#wire_strings => Ok(Self::#variant_names),)*
+                        _ => Err(::std::format!("unknown event type `{}`", s)),
+                    }
+                }
+            }
+
+            impl ::std::convert::From<&str> for EventType {
+                // `ruma_event!`'s generated `event_type()` method converts its `event_type`
+                // literal into `EventType` through this impl (see `RumaEvent::to_tokens`), so it
+                // needs to exist alongside `FromStr` rather than just `FromStr` on its own.
+                fn from(s: &str) -> Self {
+                    <Self as ::std::str::FromStr>::from_str(s)
+                        .unwrap_or_else(|err| ::std::panic!("{}", err))
+                }
+            }
+
+            impl ::std::fmt::Display for EventType {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    let wire_str = match self {
+                        #(Self::#variant_names => #wire_strings),*
+                    };
+
+                    f.write_str(wire_str)
+                }
+            }
+
+            /// The deserialized `content` of any event known to this macro invocation, tagged
+            /// by its `EventType`.
+            #[derive(Clone, Debug)]
+            pub enum AnyEventContent {
+                #(#variant_names(#content_types)),*
+            }
+
+            /// Deserializes a raw JSON event value's `content` into the concrete content type
+            /// selected by its `type` field.
+            ///
+            /// This gives downstream code a single `EventType`-keyed entry point instead of a
+            /// hand-written match arm per event.
+            pub fn from_raw_event(
+                raw: &#serde_json_path::Value,
+            ) -> #serde_json_path::Result<AnyEventContent> {
+                let event_type: EventType = raw
+                    .get("type")
+                    .and_then(#serde_json_path::Value::as_str)
+                    .and_then(|wire_str| wire_str.parse().ok())
+                    .ok_or_else(|| {
+                        <#serde_json_path::Error as #serde_path::de::Error>::custom(
+                            "missing or unrecognized `type` field",
+                        )
+                    })?;
+
+                let content =
+                    raw.get("content").cloned().unwrap_or(#serde_json_path::Value::Null);
+
+                Ok(match event_type {
+                    #(
+                        EventType::#variant_names => {
+                            AnyEventContent::#variant_names(
+                                #serde_json_path::from_value(content)?
+                            )
+                        }
+                    ),*
+                })
+            }
+        };
+
+        expanded.to_tokens(tokens);
+    }
+}
+
+/// Derives a `PascalCase` enum variant identifier from an `event_type` wire string like
+/// `"m.room.message"` (→ `RoomMessage`), dropping the leading namespace segment (`m`, `org`, …).
+fn variant_name(event_type: &LitStr) -> Ident {
+    let pascal_case = event_type
+        .value()
+        .split(|c| c == '.' || c == '_')
+        .filter(|segment| !segment.is_empty())
+        .skip(1)
+        .map(|segment| {
+            let mut chars = segment.chars();
+
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => ::std::string::String::new(),
+            }
+        })
+        .collect::<String>();
+
+    format_ident!("{}", pascal_case, span = event_type.span())
+}
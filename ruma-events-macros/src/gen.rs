@@ -2,16 +2,18 @@
 
 #![allow(dead_code)]
 
+use std::convert::TryFrom;
+
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote, ToTokens};
-use syn::{
-    parse::{self, Parse, ParseStream},
-    parse_quote,
-    punctuated::Punctuated,
-    Attribute, Field, Ident, LitStr, Token,
-};
+use syn::{parse_quote, punctuated::Punctuated, Attribute, Field, Ident, LitStr, Token};
 
-use crate::parse::{Content, EventKind, RumaEventInput};
+use crate::{
+    crate_path::CratePaths,
+    parse::{
+        combine, extract_crate_override, Content, EventKind, ParsableNamedField, RumaEventInput,
+    },
+};
 
 /// The result of processing the `ruma_event` macro, ready for output back to source code.
 pub struct RumaEvent {
@@ -21,6 +23,9 @@ pub struct RumaEvent {
     /// Information for generating the type used for the event's `content` field.
     content: Content,
 
+    /// The resolved paths used to refer to `ruma-events` and `serde` items in generated code.
+    crate_paths: CratePaths,
+
     /// The name of the type of the event's `content` field.
     content_name: Ident,
 
@@ -38,48 +43,152 @@ pub struct RumaEvent {
     name: Ident,
 }
 
-impl From<RumaEventInput> for RumaEvent {
-    fn from(input: RumaEventInput) -> Self {
+impl TryFrom<RumaEventInput> for RumaEvent {
+    type Error = syn::Error;
+
+    /// Validates the parsed macro input, accruing every problem found (rather than stopping at
+    /// the first one) so `cargo` reports them all in a single compile pass.
+    fn try_from(input: RumaEventInput) -> Result<Self, Self::Error> {
+        let mut attrs = input.attrs;
+        let mut error: Option<syn::Error> = None;
+
+        let crate_override = match extract_crate_override(&mut attrs) {
+            Ok(crate_override) => crate_override,
+            Err(err) => {
+                combine(&mut error, err);
+                None
+            }
+        };
+
+        if !input.event_type.value().contains('.') {
+            combine(
+                &mut error,
+                syn::Error::new_spanned(
+                    &input.event_type,
+                    "`event_type` should be a dotted identifier, like `m.room.message`",
+                ),
+            );
+        }
+
+        if let Content::Struct(content_fields) = &input.content {
+            check_for_reserved_field(content_fields.iter().map(|(field, _)| field), &mut error);
+        }
+        if let Some(fields) = &input.fields {
+            check_for_reserved_field(fields, &mut error);
+        }
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
         let kind = input.kind;
         let name = input.name;
         let content_name = format_ident!("{}Content", name, span = Span::call_site());
         let event_type = input.event_type;
+        let crate_paths = CratePaths::resolve(crate_override.as_ref());
 
         let mut fields =
             populate_event_fields(content_name.clone(), input.fields.unwrap_or_else(Vec::new));
 
         fields.sort_unstable_by_key(|field| field.ident.clone().unwrap());
 
-        Self {
-            attrs: input.attrs,
+        Ok(Self {
+            attrs,
             content: input.content,
+            crate_paths,
             content_name,
             event_type,
             fields,
             kind,
             name,
+        })
+    }
+}
+
+/// Checks that none of `fields` is named `content`, which is reserved for the field generated by
+/// `populate_event_fields`.
+fn check_for_reserved_field<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    error: &mut Option<syn::Error>,
+) {
+    for field in fields {
+        if field.ident.as_ref().map_or(false, |ident| ident == "content") {
+            combine(
+                error,
+                syn::Error::new_spanned(
+                    field,
+                    "`content` is a reserved field name generated by `ruma_event!`",
+                ),
+            );
         }
     }
 }
 
 impl ToTokens for RumaEvent {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        // let attrs = &self.attrs;
+        let attrs = &self.attrs;
         let content_name = &self.content_name;
-        // let event_fields = &self.fields;
-        // let event_type = &self.event_type;
+        let event_fields = &self.fields;
+        let event_type = &self.event_type;
+        let ruma_events_path = &self.crate_paths.ruma_events;
+        let serde_path = &self.crate_paths.serde;
 
         let name = &self.name;
         let content_docstring = format!("The payload for `{}`.", name);
 
         let content = match &self.content {
             Content::Struct(fields) => {
+                let struct_fields = fields.iter().map(|(field, _)| field);
+
+                // Only emit `impl Default` when at least one field actually opted in with an
+                // initializer; otherwise every field's type would be forced to implement
+                // `Default` (e.g. via the `Default::default()` fallback), which isn't true of
+                // all content fields (a `UserId`/`EventId`, for instance).
+                let default_impl = if fields.iter().any(|(_, init)| init.is_some()) {
+                    let default_fields = fields.iter().map(|(field, init)| {
+                        let ident = &field.ident;
+
+                        match init {
+                            Some(init) => quote! { #ident: #init },
+                            None => quote! { #ident: ::std::default::Default::default() },
+                        }
+                    });
+
+                    quote! {
+                        impl ::std::default::Default for #content_name {
+                            fn default() -> Self {
+                                Self {
+                                    #(#default_fields),*
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    quote! {}
+                };
+
                 quote! {
                     #[doc = #content_docstring]
-                    #[derive(Clone, Debug, ::serde::Serialize, ::serde::Deserialize)]
+                    #[derive(Clone, Debug, #serde_path::Serialize, #serde_path::Deserialize)]
                     pub struct #content_name {
-                        #(#fields),*
+                        #(#struct_fields),*
                     }
+
+                    #default_impl
+                }
+            }
+            Content::Tuple(fields) => {
+                quote! {
+                    #[doc = #content_docstring]
+                    #[derive(Clone, Debug, #serde_path::Serialize, #serde_path::Deserialize)]
+                    pub struct #content_name(#(#fields),*);
+                }
+            }
+            Content::Unit => {
+                quote! {
+                    #[doc = #content_docstring]
+                    #[derive(Clone, Debug, #serde_path::Serialize, #serde_path::Deserialize)]
+                    pub struct #content_name;
                 }
             }
             Content::Typedef(typedef) => {
@@ -94,6 +203,23 @@ impl ToTokens for RumaEvent {
         };
 
         content.to_tokens(tokens);
+
+        let event = quote! {
+            #(#attrs)*
+            #[derive(Clone, Debug, #serde_path::Serialize, #serde_path::Deserialize)]
+            pub struct #name {
+                #(#event_fields),*
+            }
+
+            impl #name {
+                /// Returns the `event_type` of this event.
+                pub fn event_type(&self) -> #ruma_events_path::EventType {
+                    ::std::convert::From::from(#event_type)
+                }
+            }
+        };
+
+        event.to_tokens(tokens);
     }
 }
 
@@ -108,20 +234,3 @@ fn populate_event_fields(content_name: Ident, mut fields: Vec<Field>) -> Vec<Fie
 
     fields
 }
-
-/// A wrapper around `syn::Field` that makes it possible to parse `Punctuated<Field, Token![,]>`
-/// from a `TokenStream`.
-///
-/// See https://github.com/dtolnay/syn/issues/651 for more context.
-struct ParsableNamedField {
-    /// The wrapped `Field`.
-    pub field: Field,
-}
-
-impl Parse for ParsableNamedField {
-    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
-        let field = Field::parse_named(input)?;
-
-        Ok(Self { field })
-    }
-}
@@ -0,0 +1,51 @@
+//! Implementation details of the `ruma-events` crate's procedural macros.
+//!
+//! These procedural macros are re-exported by the `ruma-events` crate, which is the only
+//! supported way to use them; this crate's API is not meant to be consumed directly.
+
+#![recursion_limit = "256"]
+
+extern crate proc_macro;
+
+mod crate_path;
+mod event_type;
+mod gen;
+mod parse;
+
+use std::convert::TryFrom;
+
+use proc_macro::TokenStream;
+use quote::ToTokens;
+use syn::parse_macro_input;
+
+use crate::{
+    event_type::EventTypeSet,
+    gen::RumaEvent,
+    parse::{EventTypeSetInput, RumaEventInput},
+};
+
+/// Generates an event type and its associated `content` type from a struct-like or
+/// type-alias description of the event's payload.
+///
+/// See the crate-level documentation of `ruma-events` for the accepted syntax.
+#[proc_macro]
+pub fn ruma_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as RumaEventInput);
+
+    match RumaEvent::try_from(input) {
+        Ok(event) => event.into_token_stream().into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Generates a central `EventType` enum and a content-dispatching deserializer from the full set
+/// of `"m.some.event" => SomeEventContent` mappings produced by earlier `ruma_event!` calls.
+///
+/// See the crate-level documentation of `ruma-events` for the accepted syntax.
+#[proc_macro]
+pub fn ruma_event_types(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as EventTypeSetInput);
+    let event_types = EventTypeSet::from(input);
+
+    event_types.into_token_stream().into()
+}
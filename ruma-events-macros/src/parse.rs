@@ -0,0 +1,420 @@
+//! Parsing of the `ruma_event!` macro's input syntax.
+
+use proc_macro2::TokenStream;
+use syn::{
+    braced, parenthesized,
+    parse::{self, Parse, ParseStream},
+    Attribute, Expr, Field, Ident, LitStr, Path, Token, Type, Visibility,
+};
+
+mod kw {
+    syn::custom_keyword!(kind);
+    syn::custom_keyword!(event_type);
+    syn::custom_keyword!(fields);
+    syn::custom_keyword!(content);
+}
+
+/// The kind of Matrix event being defined.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// A basic event.
+    Event,
+
+    /// A room message event.
+    Message,
+
+    /// A room state event.
+    State,
+}
+
+impl Parse for EventKind {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let ident: Ident = input.parse()?;
+
+        match ident.to_string().as_str() {
+            "Event" => Ok(Self::Event),
+            "Message" => Ok(Self::Message),
+            "State" => Ok(Self::State),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "expected one of `Event`, `Message`, `State`",
+            )),
+        }
+    }
+}
+
+/// The body of a `content` block: a set of named fields, a tuple of unnamed fields, a unit
+/// struct, or a type alias.
+pub enum Content {
+    /// A `content` block with named fields, generating a new struct, e.g. `{ pub foo: String }`.
+    ///
+    /// Each field may carry an initializer expression (`pub foo: String = "default".into()`)
+    /// used to generate `impl Default` for the content type; fields without one fall back to
+    /// `Default::default()`.
+    Struct(Vec<(Field, Option<Expr>)>),
+
+    /// A `content` block with unnamed fields, generating a tuple struct, e.g. `(pub String)`.
+    Tuple(Vec<Field>),
+
+    /// A `content` block with no fields, generating a unit struct, written as `;`.
+    Unit,
+
+    /// A `content` block that aliases an existing type.
+    Typedef(ContentTypedef),
+}
+
+impl Parse for Content {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        if input.peek(syn::token::Brace) {
+            Ok(Self::Struct(parse_braced_fields(input)?))
+        } else if input.peek(syn::token::Paren) {
+            Ok(Self::Tuple(parse_paren_fields(input)?))
+        } else if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            Ok(Self::Unit)
+        } else {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let path = input.parse()?;
+
+            Ok(Self::Typedef(ContentTypedef { attrs, path }))
+        }
+    }
+}
+
+/// A `content` block that type-aliases an existing type instead of declaring new fields.
+pub struct ContentTypedef {
+    /// Outer attributes on the type alias, such as a docstring.
+    pub attrs: Vec<Attribute>,
+
+    /// The type being aliased.
+    pub path: Path,
+}
+
+/// The parsed input to the `ruma_event!` macro.
+pub struct RumaEventInput {
+    /// Outer attributes on the event, such as a docstring.
+    pub attrs: Vec<Attribute>,
+
+    /// The kind of event being defined.
+    pub kind: EventKind,
+
+    /// The name of the event.
+    pub name: Ident,
+
+    /// The `event_type` string used on the wire, e.g. `"m.room.message"`.
+    pub event_type: LitStr,
+
+    /// Struct fields of the event in addition to the common ones.
+    pub fields: Option<Vec<Field>>,
+
+    /// The event's `content` field description.
+    pub content: Content,
+}
+
+impl Parse for RumaEventInput {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name: Ident = input.parse()?;
+
+        let body;
+        braced!(body in input);
+
+        let mut kind = None;
+        let mut event_type = None;
+        let mut fields = None;
+        let mut content = None;
+        let mut error: Option<syn::Error> = None;
+
+        while !body.is_empty() {
+            let lookahead = body.lookahead1();
+
+            let result = if lookahead.peek(kw::kind) {
+                body.parse::<kw::kind>()?;
+                body.parse::<Token![:]>()?;
+                body.parse().map(|value| kind = Some(value))
+            } else if lookahead.peek(kw::event_type) {
+                body.parse::<kw::event_type>()?;
+                body.parse::<Token![:]>()?;
+                body.parse().map(|value| event_type = Some(value))
+            } else if lookahead.peek(kw::fields) {
+                body.parse::<kw::fields>()?;
+                body.parse::<Token![:]>()?;
+                // Initializer expressions only apply to `content` fields (they generate
+                // `impl Default` for the content type), so they're dropped here.
+                parse_braced_fields(&body)
+                    .map(|value| fields = Some(value.into_iter().map(|(field, _)| field).collect()))
+            } else if lookahead.peek(kw::content) {
+                body.parse::<kw::content>()?;
+                body.parse::<Token![:]>()?;
+                body.parse().map(|value| content = Some(value))
+            } else {
+                Err(lookahead.error())
+            };
+
+            if let Err(err) = result {
+                combine(&mut error, err);
+            }
+
+            if !body.is_empty() {
+                if let Err(err) = body.parse::<Token![,]>() {
+                    combine(&mut error, err);
+                    break;
+                }
+            }
+        }
+
+        if kind.is_none() {
+            combine(&mut error, body.error("missing `kind` field"));
+        }
+        if event_type.is_none() {
+            combine(&mut error, body.error("missing `event_type` field"));
+        }
+        if content.is_none() {
+            combine(&mut error, body.error("missing `content` field"));
+        }
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        Ok(Self {
+            attrs,
+            kind: kind.unwrap(),
+            name,
+            event_type: event_type.unwrap(),
+            fields,
+            content: content.unwrap(),
+        })
+    }
+}
+
+/// A single `"m.some.event" => SomeEventContent` mapping, as given to `ruma_event_types!`.
+pub struct EventTypeMapping {
+    /// The `event_type` string used on the wire.
+    pub event_type: LitStr,
+
+    /// The event's content type, generated by a prior `ruma_event!` invocation.
+    pub content_type: Path,
+}
+
+impl Parse for EventTypeMapping {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let event_type = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let content_type = input.parse()?;
+
+        Ok(Self {
+            event_type,
+            content_type,
+        })
+    }
+}
+
+/// The parsed input to the `ruma_event_types!` macro: the full set of `event_type` ↔ content
+/// type mappings to aggregate into a central `EventType` enum and content dispatcher.
+pub struct EventTypeSetInput {
+    /// Every `event_type => ContentType` mapping given to the macro.
+    pub mappings: Vec<EventTypeMapping>,
+}
+
+impl Parse for EventTypeSetInput {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let tokens: TokenStream = input.parse()?;
+
+        let mut mappings = Vec::new();
+        let mut error: Option<syn::Error> = None;
+
+        for chunk in split_on_top_level_commas(tokens) {
+            match syn::parse2::<EventTypeMapping>(chunk) {
+                Ok(mapping) => mappings.push(mapping),
+                Err(err) => combine(&mut error, err),
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(Self { mappings }),
+        }
+    }
+}
+
+/// Accrues `err` into `error`, combining with any error already present via
+/// [`syn::Error::combine`] so every problem is reported in a single compile pass.
+pub(crate) fn combine(error: &mut Option<syn::Error>, err: syn::Error) {
+    match error {
+        Some(existing) => existing.combine(err),
+        None => *error = Some(err),
+    }
+}
+
+/// Extracts and removes a `#[ruma_event(crate = "...")]` attribute from `attrs`, if present,
+/// returning the overridden crate path. This attribute configures the macro itself rather than
+/// being carried through to the generated code.
+pub fn extract_crate_override(attrs: &mut Vec<Attribute>) -> parse::Result<Option<LitStr>> {
+    let position = match attrs.iter().position(|attr| attr.path.is_ident("ruma_event")) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let attr = attrs.remove(position);
+
+    syn::parse2::<RumaEventAttrArgs>(attr.tokens).map(|args| Some(args.crate_path))
+}
+
+/// The parenthesized body of a `#[ruma_event(crate = "...")]` attribute.
+struct RumaEventAttrArgs {
+    /// The overridden crate path, as a string to be re-parsed as a `TokenStream`.
+    crate_path: LitStr,
+}
+
+impl Parse for RumaEventAttrArgs {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+
+        content.parse::<Token![crate]>()?;
+        content.parse::<Token![=]>()?;
+        let crate_path = content.parse()?;
+
+        Ok(Self { crate_path })
+    }
+}
+
+/// Parses a brace-delimited, comma-separated list of named fields.
+///
+/// Each field is parsed independently of its neighbors (rather than as one contiguous
+/// `Punctuated` list), so a malformed field doesn't prevent the others from being checked; all
+/// of their errors are accrued and combined before being returned.
+fn parse_braced_fields(input: ParseStream<'_>) -> parse::Result<Vec<(Field, Option<Expr>)>> {
+    let body;
+    braced!(body in input);
+
+    let tokens: TokenStream = body.parse()?;
+
+    let mut fields = Vec::new();
+    let mut error: Option<syn::Error> = None;
+
+    for chunk in split_on_top_level_commas(tokens) {
+        match syn::parse2::<ParsableNamedField>(chunk) {
+            Ok(field) => fields.push((field.field, field.init)),
+            Err(err) => combine(&mut error, err),
+        }
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(fields),
+    }
+}
+
+/// Parses a parenthesized, comma-separated list of unnamed (tuple-style) fields, accruing and
+/// combining errors the same way [`parse_braced_fields`] does.
+fn parse_paren_fields(input: ParseStream<'_>) -> parse::Result<Vec<Field>> {
+    let body;
+    parenthesized!(body in input);
+
+    let tokens: TokenStream = body.parse()?;
+
+    let mut fields = Vec::new();
+    let mut error: Option<syn::Error> = None;
+
+    for chunk in split_on_top_level_commas(tokens) {
+        match syn::parse2::<ParsableUnnamedField>(chunk) {
+            Ok(field) => fields.push(field.field),
+            Err(err) => combine(&mut error, err),
+        }
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(fields),
+    }
+}
+
+/// Splits a token stream into the chunks of tokens between its top-level commas, dropping the
+/// commas themselves and any resulting empty chunks (such as from a trailing comma).
+///
+/// `()`/`[]`/`{}` are already balanced by `proc_macro2` into single `Group` trees, but `<`/`>`
+/// are ordinary `Punct`s, so a comma inside a generic type argument list (`HashMap<String, T>`)
+/// would otherwise look identical to a field-separating comma. Angle-bracket depth is tracked to
+/// tell the two apart.
+fn split_on_top_level_commas(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut chunks = vec![TokenStream::new()];
+    let mut angle_depth: i32 = 0;
+
+    for tree in tokens {
+        if let proc_macro2::TokenTree::Punct(ref punct) = tree {
+            match punct.as_char() {
+                '<' => angle_depth += 1,
+                '>' => angle_depth = (angle_depth - 1).max(0),
+                ',' if angle_depth == 0 => {
+                    chunks.push(TokenStream::new());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        chunks.last_mut().unwrap().extend(std::iter::once(tree));
+    }
+
+    chunks.into_iter().filter(|chunk| !chunk.is_empty()).collect()
+}
+
+/// A wrapper around `syn::Field` that makes it possible to parse a single named field (including
+/// via [`syn::parse2`]) since `Field` itself doesn't implement `Parse`.
+///
+/// See https://github.com/dtolnay/syn/issues/651 for more context.
+///
+/// Also accepts an optional `= <expr>` initializer after the field's type, e.g.
+/// `pub limit: u32 = 10`, which is stripped before the field is handed off for struct emission.
+pub(crate) struct ParsableNamedField {
+    /// The wrapped `Field`.
+    pub field: Field,
+
+    /// The field's initializer expression, if one was given.
+    pub init: Option<Expr>,
+}
+
+impl Parse for ParsableNamedField {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let field = Field::parse_named(input)?;
+
+        let init = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self { field, init })
+    }
+}
+
+/// A parser for a single unnamed (tuple-style) field, such as the `pub String` in
+/// `pub struct FooContent(pub String);`.
+///
+/// `syn::Field` has no public constructor for this shape, so the pieces are parsed by hand and
+/// assembled directly.
+pub(crate) struct ParsableUnnamedField {
+    /// The wrapped `Field`.
+    pub field: Field,
+}
+
+impl Parse for ParsableUnnamedField {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis: Visibility = input.parse()?;
+        let ty: Type = input.parse()?;
+
+        Ok(Self {
+            field: Field {
+                attrs,
+                vis,
+                ident: None,
+                colon_token: None,
+                ty,
+            },
+        })
+    }
+}